@@ -0,0 +1,210 @@
+//! `#[derive(Args)]`: builds the combinator tree for a struct from
+//! `#[arg(...)]`-annotated fields instead of hand-written `args_all!`/
+//! `args_map!` blocks.
+//!
+//! Each field becomes one leaf of the chain built by the `argle` crate's
+//! combinators, chosen by the field's type: `bool` is a `flag`, `Option<T>`
+//! is an optional `opt`, `Vec<T>` is an `opt_multi`, and anything else is a
+//! required `opt`. The generated `Arg` impl's `update_switches` registers
+//! every field and `get` assembles the struct from the parsed values.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericArgument, PathArguments, Type};
+
+struct FieldArgs {
+    short: Option<String>,
+    long: Option<String>,
+    doc: String,
+    hint: Option<String>,
+}
+
+impl FieldArgs {
+    /// The field's `short` attribute as a single `char`, or `None` if it
+    /// wasn't given. `short = "foo"` is a usage error, not a silently
+    /// truncated name.
+    fn short(&self, field: &syn::Field) -> syn::Result<Option<char>> {
+        self.short
+            .as_ref()
+            .map(|short| {
+                let mut chars = short.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(c),
+                    _ => Err(syn::Error::new(
+                        field.span(),
+                        "`short` must be a single character",
+                    )),
+                }
+            })
+            .transpose()
+    }
+}
+
+fn parse_field_args(field: &syn::Field) -> syn::Result<FieldArgs> {
+    let mut short = None;
+    let mut long = None;
+    let mut doc = String::new();
+    let mut hint = None;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("arg") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            let value = meta.value()?;
+            let lit: syn::LitStr = value.parse()?;
+            if meta.path.is_ident("short") {
+                short = Some(lit.value());
+            } else if meta.path.is_ident("long") {
+                long = Some(lit.value());
+            } else if meta.path.is_ident("doc") {
+                doc = lit.value();
+            } else if meta.path.is_ident("hint") {
+                hint = Some(lit.value());
+            } else {
+                return Err(meta.error("unrecognized arg attribute key"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(FieldArgs {
+        short,
+        long,
+        doc,
+        hint,
+    })
+}
+
+enum FieldShape<'a> {
+    Flag,
+    Required(&'a Type),
+    Optional(&'a Type),
+    Multi(&'a Type),
+}
+
+fn inner_generic_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    if let Type::Path(type_path) = ty {
+        let segment = type_path.path.segments.last()?;
+        if segment.ident != wrapper {
+            return None;
+        }
+        if let PathArguments::AngleBracketed(args) = &segment.arguments {
+            if let Some(GenericArgument::Type(inner)) = args.args.first() {
+                return Some(inner);
+            }
+        }
+    }
+    None
+}
+
+fn field_shape(ty: &Type) -> FieldShape<'_> {
+    if let Type::Path(type_path) = ty {
+        if type_path.path.is_ident("bool") {
+            return FieldShape::Flag;
+        }
+    }
+    if let Some(inner) = inner_generic_type(ty, "Option") {
+        return FieldShape::Optional(inner);
+    }
+    if let Some(inner) = inner_generic_type(ty, "Vec") {
+        return FieldShape::Multi(inner);
+    }
+    FieldShape::Required(ty)
+}
+
+/// Builds the `argle::names![...]` expression for a field, preferring both
+/// names when a `short` was given and falling back to just the long name
+/// (which defaults to the field's own identifier) otherwise.
+fn names_expr(span: proc_macro2::Span, short: Option<char>, long: String) -> TokenStream2 {
+    match short {
+        Some(short) => quote_spanned! { span=> argle::names![#short, #long] },
+        None => quote_spanned! { span=> argle::names![#long] },
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "#[derive(Args)] only supports structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "#[derive(Args)] only supports structs",
+            ))
+        }
+    };
+
+    let mut field_names = Vec::new();
+    let mut field_exprs = Vec::new();
+    for field in fields {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_args = parse_field_args(field)?;
+        let short = field_args.short(field)?;
+        let long = field_args
+            .long
+            .clone()
+            .unwrap_or_else(|| field_name.to_string());
+        let names = names_expr(field.span(), short, long);
+        let doc = &field_args.doc;
+        let hint = field_args.hint.unwrap_or_default();
+        let expr = match field_shape(&field.ty) {
+            FieldShape::Flag => quote_spanned! { field.span()=>
+                argle::flag(#names, #doc)
+            },
+            FieldShape::Required(ty) => quote_spanned! { field.span()=>
+                argle::Arg::required(argle::opt::<#ty>(#names, #doc, #hint))
+            },
+            FieldShape::Optional(ty) => quote_spanned! { field.span()=>
+                argle::opt::<#ty>(#names, #doc, #hint)
+            },
+            FieldShape::Multi(ty) => quote_spanned! { field.span()=>
+                argle::opt_multi::<#ty>(#names, #doc, #hint)
+            },
+        };
+        field_names.push(field_name);
+        field_exprs.push(expr);
+    }
+
+    Ok(quote! {
+        impl argle::Arg for #name {
+            type Item = #name;
+            type Error = ::std::string::String;
+
+            fn update_switches<S: argle::Switches>(&self, switches: &mut S) {
+                #( argle::Arg::update_switches(&(#field_exprs), switches); )*
+            }
+
+            fn name(&self) -> ::std::string::String {
+                ::std::stringify!(#name).to_string()
+            }
+
+            fn get(self, matches: &argle::Matches) -> ::std::result::Result<Self::Item, Self::Error> {
+                #(
+                    let #field_names = argle::Arg::get(#field_exprs, matches)
+                        .map_err(|error| ::std::format!("{}", error))?;
+                )*
+                ::std::result::Result::Ok(#name { #( #field_names ),* })
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(Args, attributes(arg))]
+pub fn derive_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
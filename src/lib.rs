@@ -1,39 +1,115 @@
-extern crate getopts;
-
 use std::env;
 use std::ffi::OsStr;
 use std::fmt;
 use std::process;
 use std::str::FromStr;
 
+// Lets `#[derive(Args)]` output expanded inside this crate (e.g. in our own
+// tests) refer to this crate as `argle::...`, the same paths it uses in a
+// downstream crate. This only affects paths within this crate: downstream,
+// those paths resolve only if the dependency is named `argle`.
+extern crate self as argle;
+
+mod lowlevel;
+mod positional;
+mod subcommand;
 mod util;
 mod validation;
+pub use lowlevel::{ArgName, LowLevelError, Matches};
+pub use positional::{pos, variadic, Positional, Positionals, PositionalsInvalid, VariadicPositional};
+pub use subcommand::{
+    command, subcommand, Command, Subcommand, SubcommandError, SubcommandList, SubcommandOrHelp,
+    SubcommandParseResult, SubcommandWithHelp, Subcommands, SubcommandsUsage,
+};
 pub use util::Never;
 pub use validation::Invalid;
 
-pub type Matches = getopts::Matches;
+/// `#[derive(Args)]`, letting a struct's `#[arg(...)]`-annotated fields
+/// stand in for a hand-written `args_all!`/`args_map!` combinator chain.
+#[cfg(feature = "derive")]
+pub use argle_derive::Args;
+
+/// The identity of a single switch spelling: either its short (`-x`) form or
+/// its long (`--xyz`) form. A switch may be registered under one or both, by
+/// passing more than one `Name` to a constructor.
+#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+pub enum Name {
+    Short(char),
+    Long(String),
+}
+
+/// Converts a convenient Rust value into a `Name`, so constructors can take
+/// `'h'` for a short name and `"help"` for a long one instead of requiring
+/// `Name::Short`/`Name::Long` to be spelled out.
+pub trait IntoName {
+    fn into_name(self) -> Name;
+}
+
+impl IntoName for char {
+    fn into_name(self) -> Name {
+        Name::Short(self)
+    }
+}
+
+impl IntoName for &str {
+    fn into_name(self) -> Name {
+        Name::Long(self.to_string())
+    }
+}
+
+impl IntoName for String {
+    fn into_name(self) -> Name {
+        Name::Long(self)
+    }
+}
+
+/// Builds an array of `Name`s from a mix of `char`s and string-likes, e.g.
+/// `names!['h', "help"]`, for passing to a switch constructor.
+#[macro_export]
+macro_rules! names {
+    ( $($name:expr),+ $(,)? ) => {
+        [ $( $crate::IntoName::into_name($name) ),+ ]
+    };
+}
 
 #[derive(Hash, PartialEq, Eq, Clone, Debug)]
 pub struct SwitchCommon {
-    pub short: String,
-    pub long: String,
+    pub short: Option<char>,
+    pub long: Option<String>,
     pub doc: String,
 }
 
 impl SwitchCommon {
-    fn new(short: &str, long: &str, doc: &str) -> Self {
+    fn new(names: impl IntoIterator<Item = Name>, doc: &str) -> Self {
+        let mut short = None;
+        let mut long = None;
+        for name in names {
+            match name {
+                Name::Short(c) => short = Some(c),
+                Name::Long(l) => long = Some(l),
+            }
+        }
         Self {
-            short: short.to_string(),
-            long: long.to_string(),
+            short,
+            long,
             doc: doc.to_string(),
         }
     }
 
-    fn key_to_search_in_matches(&self) -> &str {
-        if self.short.len() != 0 {
-            self.short.as_str()
-        } else {
-            self.long.as_str()
+    /// The key switches are recorded and looked up under in `Matches`,
+    /// preferring the short name when both are registered.
+    pub(crate) fn key_to_search_in_matches(&self) -> String {
+        match self.short {
+            Some(c) => c.to_string(),
+            None => self.long.clone().unwrap_or_default(),
+        }
+    }
+
+    /// A human-readable name for error messages, preferring the long name.
+    pub(crate) fn display_name(&self) -> String {
+        match &self.long {
+            Some(long) => long.clone(),
+            None => self.short.map(|c| c.to_string()).unwrap_or_default(),
         }
     }
 }
@@ -42,58 +118,54 @@ impl SwitchCommon {
 pub enum SwitchShape {
     Flag,
     Opt { hint: String },
+    MultiOpt { hint: String },
+    Count,
 }
 
-pub trait Switches {
-    fn add(&mut self, common: SwitchCommon, shape: SwitchShape);
-}
+impl SwitchShape {
+    pub(crate) fn takes_param(&self) -> bool {
+        matches!(self, Self::Opt { .. } | Self::MultiOpt { .. })
+    }
 
-impl Switches for getopts::Options {
-    fn add(&mut self, common: SwitchCommon, arity: SwitchShape) {
-        match arity {
-            SwitchShape::Flag => {
-                self.optflag(
-                    common.short.as_str(),
-                    common.long.as_str(),
-                    common.doc.as_str(),
-                );
-            }
-            SwitchShape::Opt { hint } => {
-                self.optopt(
-                    common.short.as_str(),
-                    common.long.as_str(),
-                    common.doc.as_str(),
-                    hint.as_str(),
-                );
-            }
+    pub(crate) fn hint(&self) -> Option<&str> {
+        match self {
+            Self::Opt { hint } | Self::MultiOpt { hint } => Some(hint.as_str()),
+            Self::Flag | Self::Count => None,
         }
     }
 }
 
+pub trait Switches {
+    fn add(&mut self, common: SwitchCommon, shape: SwitchShape);
+}
+
 #[derive(Debug)]
 pub enum TopLevelError<E> {
-    Getopts(getopts::Fail),
+    Parse(LowLevelError),
+    UnhandledPositionalArguments { unhandled: Vec<String> },
     Other(E),
 }
 
 impl<E: fmt::Display> fmt::Display for TopLevelError<E> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match self {
-            Self::Getopts(fail) => fmt::Display::fmt(&fail, f),
+            Self::Parse(error) => fmt::Display::fmt(&error, f),
+            Self::UnhandledPositionalArguments { unhandled } => {
+                write!(f, "unexpected argument(s): {}", unhandled.join(" "))
+            }
             Self::Other(other) => fmt::Display::fmt(&other, f),
         }
     }
 }
 
 pub struct Usage {
-    opts: getopts::Options,
+    spec: lowlevel::Spec,
     program_name: String,
 }
 
 impl Usage {
     pub fn render(&self) -> String {
-        let brief = format!("Usage: {} [options]", &self.program_name);
-        self.opts.usage(&brief)
+        self.spec.render(&self.program_name)
     }
 }
 
@@ -106,6 +178,10 @@ pub trait Arg: Sized {
     type Item;
     type Error: fmt::Debug + fmt::Display;
     fn update_switches<S: Switches>(&self, switches: &mut S);
+    /// Register any positional arguments this `Arg` is made up of, in
+    /// left-to-right composition order. Most `Arg`s have none, so the
+    /// default does nothing.
+    fn update_positionals<P: positional::Positionals>(&self, _positionals: &mut P) {}
     fn name(&self) -> String;
     fn get(self, matches: &Matches) -> Result<Self::Item, Self::Error>;
     fn validate(&self) -> Option<Invalid> {
@@ -113,6 +189,11 @@ pub trait Arg: Sized {
         self.update_switches(&mut checker);
         checker.invalid()
     }
+    fn validate_positionals(&self) -> Option<PositionalsInvalid> {
+        let mut checker = positional::PositionalsChecker::default();
+        self.update_positionals(&mut checker);
+        checker.invalid()
+    }
     fn parse_specified_ignoring_validation<I>(
         self,
         program_name: String,
@@ -122,14 +203,27 @@ pub trait Arg: Sized {
         I: IntoIterator,
         I::Item: AsRef<OsStr>,
     {
-        let mut opts = getopts::Options::new();
-        self.update_switches(&mut opts);
+        let mut spec = lowlevel::Spec::default();
+        self.update_switches(&mut spec);
+        let mut positionals = positional::PositionalsRegistrar::default();
+        self.update_positionals(&mut positionals);
+        let args = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
         ParseResult {
-            result: opts
-                .parse(args)
-                .map_err(TopLevelError::Getopts)
-                .and_then(|matches| self.get(&matches).map_err(TopLevelError::Other)),
-            usage: Usage { opts, program_name },
+            result: lowlevel::parse(&spec, &args)
+                .map_err(TopLevelError::Parse)
+                .and_then(|matches| {
+                    if !positionals.has_variadic() && matches.free.len() > positionals.count() {
+                        Err(TopLevelError::UnhandledPositionalArguments {
+                            unhandled: matches.free[positionals.count()..].to_vec(),
+                        })
+                    } else {
+                        self.get(&matches).map_err(TopLevelError::Other)
+                    }
+                }),
+            usage: Usage { spec, program_name },
         }
     }
     fn parse_specified<I>(
@@ -144,6 +238,9 @@ pub trait Arg: Sized {
         if let Some(invalid) = self.validate() {
             panic!("Invalid command spec:\n{}", invalid);
         }
+        if let Some(invalid) = self.validate_positionals() {
+            panic!("Invalid command spec:\n{}", invalid);
+        }
         self.parse_specified_ignoring_validation(program_name, args)
     }
     fn parse_env(self) -> ParseResult<Self::Item, Self::Error> {
@@ -158,7 +255,7 @@ pub trait Arg: Sized {
         }
     }
     fn with_help_default(self) -> WithHelp<Self> {
-        self.with_help(Flag::new("h", "help", "print this help menu"))
+        self.with_help(Flag::new(names!['h', "help"], "print this help menu"))
     }
     fn option_map<F, T, U>(self, f: F) -> OptionMap<Self, F>
     where
@@ -199,6 +296,12 @@ pub trait Arg: Sized {
     {
         OptionConvertString { arg: self, f }
     }
+    fn convert_strings<F, T, E>(self, f: F) -> ConvertStrings<Self, F>
+    where
+        F: Fn(&str) -> Result<T, E>,
+    {
+        ConvertStrings { arg: self, f }
+    }
 }
 
 pub struct Flag {
@@ -206,9 +309,9 @@ pub struct Flag {
 }
 
 impl Flag {
-    pub fn new(short: &str, long: &str, doc: &str) -> Self {
+    pub fn new(names: impl IntoIterator<Item = Name>, doc: &str) -> Self {
         Self {
-            common: SwitchCommon::new(short, long, doc),
+            common: SwitchCommon::new(names, doc),
         }
     }
 }
@@ -219,10 +322,10 @@ impl Arg for Flag {
         switches.add(self.common.clone(), SwitchShape::Flag);
     }
     fn name(&self) -> String {
-        self.common.long.clone()
+        self.common.display_name()
     }
     fn get(self, matches: &Matches) -> Result<Self::Item, Self::Error> {
-        Ok(matches.opt_present(self.common.key_to_search_in_matches()))
+        Ok(matches.opt_present(&self.common.key_to_search_in_matches()))
     }
 }
 
@@ -232,9 +335,9 @@ pub struct Opt {
 }
 
 impl Opt {
-    pub fn new(short: &str, long: &str, doc: &str, hint: &str) -> Self {
+    pub fn new(names: impl IntoIterator<Item = Name>, doc: &str, hint: &str) -> Self {
         Self {
-            common: SwitchCommon::new(short, long, doc),
+            common: SwitchCommon::new(names, doc),
             hint: hint.to_string(),
         }
     }
@@ -252,10 +355,69 @@ impl Arg for Opt {
         );
     }
     fn name(&self) -> String {
-        self.common.long.clone()
+        self.common.display_name()
     }
     fn get(self, matches: &Matches) -> Result<Self::Item, Self::Error> {
-        Ok(matches.opt_str(self.common.key_to_search_in_matches()))
+        Ok(matches.opt_str(&self.common.key_to_search_in_matches()))
+    }
+}
+
+pub struct MultiOpt {
+    common: SwitchCommon,
+    hint: String,
+}
+
+impl MultiOpt {
+    pub fn new(names: impl IntoIterator<Item = Name>, doc: &str, hint: &str) -> Self {
+        Self {
+            common: SwitchCommon::new(names, doc),
+            hint: hint.to_string(),
+        }
+    }
+}
+
+impl Arg for MultiOpt {
+    type Item = Vec<String>;
+    type Error = Never;
+    fn update_switches<S: Switches>(&self, switches: &mut S) {
+        switches.add(
+            self.common.clone(),
+            SwitchShape::MultiOpt {
+                hint: self.hint.clone(),
+            },
+        );
+    }
+    fn name(&self) -> String {
+        self.common.display_name()
+    }
+    fn get(self, matches: &Matches) -> Result<Self::Item, Self::Error> {
+        Ok(matches.opt_strs(&self.common.key_to_search_in_matches()))
+    }
+}
+
+pub struct CountFlag {
+    common: SwitchCommon,
+}
+
+impl CountFlag {
+    pub fn new(names: impl IntoIterator<Item = Name>, doc: &str) -> Self {
+        Self {
+            common: SwitchCommon::new(names, doc),
+        }
+    }
+}
+
+impl Arg for CountFlag {
+    type Item = usize;
+    type Error = Never;
+    fn update_switches<S: Switches>(&self, switches: &mut S) {
+        switches.add(self.common.clone(), SwitchShape::Count);
+    }
+    fn name(&self) -> String {
+        self.common.display_name()
+    }
+    fn get(self, matches: &Matches) -> Result<Self::Item, Self::Error> {
+        Ok(matches.opt_count(&self.common.key_to_search_in_matches()))
     }
 }
 
@@ -279,6 +441,9 @@ where
         self.arg.update_switches(switches);
         self.help_flag.update_switches(switches);
     }
+    fn update_positionals<P: positional::Positionals>(&self, positionals: &mut P) {
+        self.arg.update_positionals(positionals);
+    }
     fn name(&self) -> String {
         format!("({}) with help", self.arg.name())
     }
@@ -330,6 +495,9 @@ where
     fn update_switches<S: Switches>(&self, switches: &mut S) {
         self.arg.update_switches(switches);
     }
+    fn update_positionals<P: positional::Positionals>(&self, positionals: &mut P) {
+        self.arg.update_positionals(positionals);
+    }
     fn name(&self) -> String {
         self.arg.name()
     }
@@ -356,6 +524,9 @@ where
     fn update_switches<S: Switches>(&self, switches: &mut S) {
         self.arg.update_switches(switches);
     }
+    fn update_positionals<P: positional::Positionals>(&self, positionals: &mut P) {
+        self.arg.update_positionals(positionals);
+    }
     fn name(&self) -> String {
         self.arg.name()
     }
@@ -409,6 +580,10 @@ where
         self.a.update_switches(switches);
         self.b.update_switches(switches);
     }
+    fn update_positionals<P: positional::Positionals>(&self, positionals: &mut P) {
+        self.a.update_positionals(positionals);
+        self.b.update_positionals(positionals);
+    }
     fn name(&self) -> String {
         format!("choose ({}) or ({})", self.a.name(), self.b.name())
     }
@@ -471,6 +646,10 @@ where
         self.a.update_switches(switches);
         self.b.update_switches(switches);
     }
+    fn update_positionals<P: positional::Positionals>(&self, positionals: &mut P) {
+        self.a.update_positionals(positionals);
+        self.b.update_positionals(positionals);
+    }
     fn name(&self) -> String {
         format!("({} and {})", self.a.name(), self.b.name())
     }
@@ -499,6 +678,9 @@ where
     fn update_switches<S: Switches>(&self, switches: &mut S) {
         self.arg.update_switches(switches);
     }
+    fn update_positionals<P: positional::Positionals>(&self, positionals: &mut P) {
+        self.arg.update_positionals(positionals);
+    }
     fn name(&self) -> String {
         self.arg.name()
     }
@@ -570,6 +752,9 @@ where
     fn update_switches<S: Switches>(&self, switches: &mut S) {
         self.arg.update_switches(switches);
     }
+    fn update_positionals<P: positional::Positionals>(&self, positionals: &mut P) {
+        self.arg.update_positionals(positionals);
+    }
     fn name(&self) -> String {
         self.arg.name()
     }
@@ -633,6 +818,9 @@ where
     fn update_switches<S: Switches>(&self, switches: &mut S) {
         self.arg.update_switches(switches);
     }
+    fn update_positionals<P: positional::Positionals>(&self, positionals: &mut P) {
+        self.arg.update_positionals(positionals);
+    }
     fn name(&self) -> String {
         self.arg.name()
     }
@@ -652,13 +840,89 @@ where
     }
 }
 
-pub fn flag(short: &str, long: &str, doc: &str) -> impl Arg<Item = bool> {
-    Flag::new(short, long, doc)
+pub struct ConvertStrings<A, F>
+where
+    A: Arg,
+{
+    arg: A,
+    f: F,
+}
+
+#[derive(Debug)]
+pub enum ConvertStringsError<A, E> {
+    Arg(A),
+    FailedToConvert {
+        name: String,
+        index: usize,
+        arg_string: String,
+        error: E,
+    },
+}
+
+impl<A, E> fmt::Display for ConvertStringsError<A, E>
+where
+    A: fmt::Display,
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::Arg(a) => a.fmt(f),
+            Self::FailedToConvert {
+                name,
+                index,
+                arg_string,
+                error,
+            } => write!(
+                f,
+                "failed to convert argument ({}), occurrence {}. \"{}\" could not be parsed (error: {})",
+                name, index, arg_string, error
+            ),
+        }
+    }
+}
+
+impl<A, F, T, E> Arg for ConvertStrings<A, F>
+where
+    A: Arg<Item = Vec<String>>,
+    F: Fn(&str) -> Result<T, E>,
+    E: fmt::Display + fmt::Debug,
+{
+    type Item = Vec<T>;
+    type Error = ConvertStringsError<A::Error, E>;
+    fn update_switches<S: Switches>(&self, switches: &mut S) {
+        self.arg.update_switches(switches);
+    }
+    fn update_positionals<P: positional::Positionals>(&self, positionals: &mut P) {
+        self.arg.update_positionals(positionals);
+    }
+    fn name(&self) -> String {
+        self.arg.name()
+    }
+    fn get(self, matches: &Matches) -> Result<Self::Item, Self::Error> {
+        let name = self.name();
+        let Self { arg, f } = self;
+        arg.get(matches)
+            .map_err(ConvertStringsError::Arg)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, arg_string)| {
+                f(arg_string.as_str()).map_err(|error| ConvertStringsError::FailedToConvert {
+                    name: name.clone(),
+                    index,
+                    arg_string,
+                    error,
+                })
+            })
+            .collect()
+    }
+}
+
+pub fn flag(names: impl IntoIterator<Item = Name>, doc: &str) -> impl Arg<Item = bool> {
+    Flag::new(names, doc)
 }
 
 pub fn opt<T>(
-    short: &str,
-    long: &str,
+    names: impl IntoIterator<Item = Name>,
     doc: &str,
     hint: &str,
 ) -> impl Arg<Item = Option<T>>
@@ -666,7 +930,23 @@ where
     T: FromStr,
     <T as FromStr>::Err: fmt::Debug + fmt::Display,
 {
-    Opt::new(short, long, doc, hint).option_convert_string(|s| s.parse())
+    Opt::new(names, doc, hint).option_convert_string(|s| s.parse())
+}
+
+pub fn opt_multi<T>(
+    names: impl IntoIterator<Item = Name>,
+    doc: &str,
+    hint: &str,
+) -> impl Arg<Item = Vec<T>>
+where
+    T: FromStr,
+    <T as FromStr>::Err: fmt::Debug + fmt::Display,
+{
+    MultiOpt::new(names, doc, hint).convert_strings(|s| s.parse())
+}
+
+pub fn count_flag(names: impl IntoIterator<Item = Name>, doc: &str) -> impl Arg<Item = usize> {
+    CountFlag::new(names, doc)
 }
 
 #[macro_export]
@@ -717,11 +997,12 @@ macro_rules! args_map {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
 
     #[test]
     fn basic() {
         assert_eq!(
-            opt::<u32>("f", "foo", "", "")
+            opt::<u32>(names!['f', "foo"], "", "")
                 .required()
                 .parse_specified("".to_string(), &["--foo", "42"])
                 .result
@@ -735,8 +1016,8 @@ mod tests {
         assert_eq!(
             args_map! {
                 let {
-                    a = opt::<u32>("f", "foo", "", "").required();
-                    b = opt::<u32>("b", "bar", "", "").required();
+                    a = opt::<u32>(names!['f', "foo"], "", "").required();
+                    b = opt::<u32>(names!['b', "bar"], "", "").required();
                 } in {
                     a + b
                 }
@@ -747,4 +1028,208 @@ mod tests {
             16
         );
     }
+
+    #[test]
+    fn positional_basic() {
+        assert_eq!(
+            pos::<u32>("", "NUM")
+                .required()
+                .parse_specified("".to_string(), &["42"])
+                .result
+                .unwrap(),
+            42
+        );
+    }
+
+    #[test]
+    fn positional_unhandled() {
+        assert!(pos::<u32>("", "NUM")
+            .required()
+            .parse_specified("".to_string(), &["42", "43"])
+            .result
+            .is_err());
+    }
+
+    #[test]
+    fn positional_multiple_variadic_rejected() {
+        use positional::PositionalsInvalid;
+
+        let arg = variadic::<u32>("first", "NUMS").both(variadic::<u32>("second", "MORE"));
+        assert!(matches!(
+            arg.validate_positionals(),
+            Some(PositionalsInvalid::MultipleVariadicPositionalArgs { .. })
+        ));
+    }
+
+    #[test]
+    fn variadic_basic() {
+        assert_eq!(
+            variadic::<u32>("", "NUMS")
+                .parse_specified("".to_string(), &["1", "2", "3"])
+                .result
+                .unwrap(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn opt_multi_basic() {
+        assert_eq!(
+            opt_multi::<PathBuf>(names!['I', "include"], "", "PATH")
+                .parse_specified(
+                    "".to_string(),
+                    &["-I", "a", "--include", "b", "-Ic"]
+                )
+                .result
+                .unwrap(),
+            vec![PathBuf::from("a"), PathBuf::from("b"), PathBuf::from("c")]
+        );
+    }
+
+    #[test]
+    fn count_flag_basic() {
+        assert_eq!(
+            count_flag(names!['v', "verbose"], "")
+                .parse_specified("".to_string(), &["-vvv"])
+                .result
+                .unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    fn long_name_equals_value() {
+        assert_eq!(
+            opt::<u32>(names!['f', "foo"], "", "")
+                .required()
+                .parse_specified("".to_string(), &["--foo=42"])
+                .result
+                .unwrap(),
+            42
+        );
+    }
+
+    #[test]
+    fn clustered_short_flags() {
+        let mut spec = lowlevel::Spec::default();
+        flag(names!['a', "aaa"], "").update_switches(&mut spec);
+        flag(names!['b', "bbb"], "").update_switches(&mut spec);
+        opt::<u32>(names!['c', "ccc"], "", "").update_switches(&mut spec);
+        let matches = lowlevel::parse(&spec, &["-abc7"]).unwrap();
+        assert!(matches.opt_present("a"));
+        assert!(matches.opt_present("b"));
+        assert_eq!(matches.opt_str("c"), Some("7".to_string()));
+    }
+
+    #[test]
+    fn double_dash_terminator() {
+        assert_eq!(
+            variadic::<String>("", "ARGS")
+                .parse_specified("".to_string(), &["--", "--foo", "bar"])
+                .result
+                .unwrap(),
+            vec!["--foo".to_string(), "bar".to_string()]
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Cmd {
+        Build(u32),
+        Run,
+    }
+
+    macro_rules! example_subcommands {
+        () => {
+            crate::subcommands! {
+                command(
+                    "build",
+                    opt::<u32>(names!['j', "jobs"], "", "N").with_default(0).map(Cmd::Build)
+                ),
+                command(
+                    "run",
+                    opt::<u32>(names!['n', "number"], "", "N").with_default(0).map(|_| Cmd::Run)
+                ),
+            }
+        };
+    }
+
+    #[test]
+    fn subcommand_dispatch() {
+        assert_eq!(
+            subcommand(example_subcommands!())
+                .parse_specified("".to_string(), &["build", "--jobs", "4"])
+                .result
+                .unwrap(),
+            Cmd::Build(4)
+        );
+        assert_eq!(
+            subcommand(example_subcommands!())
+                .parse_specified("".to_string(), &["run"])
+                .result
+                .unwrap(),
+            Cmd::Run
+        );
+    }
+
+    #[test]
+    fn subcommand_unknown() {
+        assert!(matches!(
+            subcommand(example_subcommands!())
+                .parse_specified("".to_string(), &["frobnicate"])
+                .result,
+            Err(SubcommandError::UnknownSubcommand { .. })
+        ));
+    }
+
+    #[test]
+    fn subcommand_missing() {
+        assert!(matches!(
+            subcommand(example_subcommands!())
+                .parse_specified::<&[&str]>("".to_string(), &[])
+                .result,
+            Err(SubcommandError::NoSubcommandGiven)
+        ));
+    }
+
+    #[cfg(feature = "derive")]
+    #[derive(Args, Debug, PartialEq)]
+    struct DeriveCli {
+        #[arg(short = "v", long = "verbose")]
+        verbose: bool,
+        #[arg(long = "name", hint = "NAME")]
+        name: Option<String>,
+        #[arg(short = "o", long = "output", hint = "PATH")]
+        output: PathBuf,
+        #[arg(short = "I", long = "include", hint = "PATH")]
+        includes: Vec<PathBuf>,
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_args_basic() {
+        // `update_switches`/`get` read only the `#[arg(...)]` metadata, never
+        // `self`'s fields, so any instance can be used to kick off parsing.
+        let spec = DeriveCli {
+            verbose: false,
+            name: None,
+            output: PathBuf::new(),
+            includes: Vec::new(),
+        };
+        let cli = spec
+            .parse_specified(
+                "".to_string(),
+                &["-v", "--name", "bob", "-o", "out.txt", "-I", "a", "-I", "b"],
+            )
+            .result
+            .unwrap();
+        assert_eq!(
+            cli,
+            DeriveCli {
+                verbose: true,
+                name: Some("bob".to_string()),
+                output: PathBuf::from("out.txt"),
+                includes: vec![PathBuf::from("a"), PathBuf::from("b")],
+            }
+        );
+    }
 }
@@ -0,0 +1,233 @@
+//! An in-crate replacement for the `getopts`-backed argument tokenizer.
+//!
+//! `Spec` plays the role `getopts::Options` used to play: `update_switches`
+//! populates it via the `Switches` trait, after which it knows every
+//! registered short/long name and whether each expects a parameter. `parse`
+//! then tokenizes `argv` against that table, producing a `Matches` that the
+//! `Arg::get` implementations read through instead of reaching into
+//! `getopts::Matches` directly.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{SwitchCommon, SwitchShape, Switches};
+
+/// Identifies a switch the way it appeared on the command line, for use in
+/// error messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgName {
+    Short(char),
+    Long(String),
+}
+
+impl fmt::Display for ArgName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::Short(c) => write!(f, "-{}", c),
+            Self::Long(name) => write!(f, "--{}", name),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LowLevelError {
+    UnknownName(ArgName),
+    ArgumentLacksParameter(ArgName),
+    UnexpectedArgumentParam { name: ArgName, param: String },
+    ExpectedOneArgument(ArgName),
+}
+
+impl fmt::Display for LowLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::UnknownName(name) => write!(f, "unrecognized option '{}'", name),
+            Self::ArgumentLacksParameter(name) => {
+                write!(f, "option '{}' requires an argument", name)
+            }
+            Self::UnexpectedArgumentParam { name, param } => write!(
+                f,
+                "option '{}' does not take an argument but was given '{}'",
+                name, param
+            ),
+            Self::ExpectedOneArgument(name) => {
+                write!(f, "option '{}' was given more than once", name)
+            }
+        }
+    }
+}
+
+struct Entry {
+    common: SwitchCommon,
+    shape: SwitchShape,
+}
+
+/// The table of registered switches, built by walking an `Arg` tree with
+/// `update_switches`. Plays the role `getopts::Options` used to play.
+#[derive(Default)]
+pub struct Spec {
+    entries: Vec<Entry>,
+    by_short: HashMap<char, usize>,
+    by_long: HashMap<String, usize>,
+}
+
+impl Spec {
+    fn lookup_short(&self, c: char) -> Option<&Entry> {
+        self.by_short.get(&c).map(|&index| &self.entries[index])
+    }
+
+    fn lookup_long(&self, name: &str) -> Option<&Entry> {
+        self.by_long.get(name).map(|&index| &self.entries[index])
+    }
+
+    /// Render a getopts-style usage message listing every registered switch.
+    pub fn render(&self, program_name: &str) -> String {
+        let mut out = format!("Usage: {} [options]\n\nOptions:\n", program_name);
+        for entry in &self.entries {
+            let names = match (&entry.common.short, &entry.common.long) {
+                (Some(short), Some(long)) => format!("-{}, --{}", short, long),
+                (Some(short), None) => format!("-{}", short),
+                (None, Some(long)) => format!("--{}", long),
+                (None, None) => String::new(),
+            };
+            let names = match entry.shape.hint() {
+                Some(hint) => format!("{} {}", names, hint),
+                None => names,
+            };
+            out.push_str(&format!("    {:<24} {}\n", names, entry.common.doc));
+        }
+        out
+    }
+}
+
+impl Switches for Spec {
+    fn add(&mut self, common: SwitchCommon, shape: SwitchShape) {
+        let index = self.entries.len();
+        if let Some(c) = common.short {
+            self.by_short.insert(c, index);
+        }
+        if let Some(long) = &common.long {
+            self.by_long.insert(long.clone(), index);
+        }
+        self.entries.push(Entry { common, shape });
+    }
+}
+
+/// The result of tokenizing `argv` against a `Spec`, read by `Arg::get`
+/// implementations in place of `getopts::Matches`.
+#[derive(Default)]
+pub struct Matches {
+    opts: HashMap<String, Vec<String>>,
+    counts: HashMap<String, usize>,
+    pub free: Vec<String>,
+}
+
+impl Matches {
+    pub fn opt_present(&self, key: &str) -> bool {
+        self.opts.get(key).is_some_and(|values| !values.is_empty())
+            || self.counts.get(key).is_some_and(|&count| count > 0)
+    }
+
+    pub fn opt_str(&self, key: &str) -> Option<String> {
+        self.opts.get(key).and_then(|values| values.last().cloned())
+    }
+
+    pub fn opt_strs(&self, key: &str) -> Vec<String> {
+        self.opts.get(key).cloned().unwrap_or_default()
+    }
+
+    pub fn opt_count(&self, key: &str) -> usize {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+}
+
+fn record_value(
+    matches: &mut Matches,
+    entry: &Entry,
+    name: ArgName,
+    value: String,
+) -> Result<(), LowLevelError> {
+    let key = entry.common.key_to_search_in_matches();
+    let values = matches.opts.entry(key).or_default();
+    if let SwitchShape::Opt { .. } = entry.shape {
+        if !values.is_empty() {
+            return Err(LowLevelError::ExpectedOneArgument(name));
+        }
+    }
+    values.push(value);
+    Ok(())
+}
+
+fn record_flag(matches: &mut Matches, entry: &Entry) {
+    let key = entry.common.key_to_search_in_matches();
+    *matches.counts.entry(key).or_insert(0) += 1;
+}
+
+/// Tokenize `args` against `spec`, producing the switch/positional split
+/// that `Arg::get` implementations consume.
+pub fn parse<S: AsRef<str>>(spec: &Spec, args: &[S]) -> Result<Matches, LowLevelError> {
+    let mut matches = Matches::default();
+    let mut terminated = false;
+    let mut iter = args.iter().map(AsRef::as_ref);
+    while let Some(arg) = iter.next() {
+        if terminated {
+            matches.free.push(arg.to_string());
+            continue;
+        }
+        if arg == "--" {
+            terminated = true;
+            continue;
+        }
+        if let Some(rest) = arg.strip_prefix("--") {
+            let (name, attached_value) = match rest.find('=') {
+                Some(eq_index) => (&rest[..eq_index], Some(rest[eq_index + 1..].to_string())),
+                None => (rest, None),
+            };
+            let entry = spec
+                .lookup_long(name)
+                .ok_or_else(|| LowLevelError::UnknownName(ArgName::Long(name.to_string())))?;
+            if entry.shape.takes_param() {
+                let value = match attached_value {
+                    Some(value) => value,
+                    None => iter.next().map(str::to_string).ok_or_else(|| {
+                        LowLevelError::ArgumentLacksParameter(ArgName::Long(name.to_string()))
+                    })?,
+                };
+                record_value(&mut matches, entry, ArgName::Long(name.to_string()), value)?;
+            } else if let Some(param) = attached_value {
+                return Err(LowLevelError::UnexpectedArgumentParam {
+                    name: ArgName::Long(name.to_string()),
+                    param,
+                });
+            } else {
+                record_flag(&mut matches, entry);
+            }
+        } else if arg.starts_with('-') && arg != "-" {
+            let chars: Vec<char> = arg[1..].chars().collect();
+            let mut index = 0;
+            while index < chars.len() {
+                let c = chars[index];
+                let entry = spec
+                    .lookup_short(c)
+                    .ok_or(LowLevelError::UnknownName(ArgName::Short(c)))?;
+                if entry.shape.takes_param() {
+                    let attached: String = chars[index + 1..].iter().collect();
+                    let value = if !attached.is_empty() {
+                        attached
+                    } else {
+                        iter.next()
+                            .map(str::to_string)
+                            .ok_or(LowLevelError::ArgumentLacksParameter(ArgName::Short(c)))?
+                    };
+                    record_value(&mut matches, entry, ArgName::Short(c), value)?;
+                    break;
+                } else {
+                    record_flag(&mut matches, entry);
+                    index += 1;
+                }
+            }
+        } else {
+            matches.free.push(arg.to_string());
+        }
+    }
+    Ok(matches)
+}
@@ -0,0 +1,198 @@
+//! Positional arguments (the leftover tokens getopts calls `free`), as
+//! opposed to the named switches handled by `Switches`/`SwitchShape`.
+//!
+//! Positionals are registered in left-to-right composition order by walking
+//! the `Arg` tree with `update_positionals`, exactly as `update_switches`
+//! walks it to build up the named switches. Each leaf records the ordinal
+//! index it was assigned so that `get` can later read `matches.free[index]`.
+
+use std::cell::Cell;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Arg, Matches, Never, Switches};
+
+/// Collects positional args during a walk of an `Arg` tree, assigning each
+/// one the ordinal index (0, 1, 2, ...) it occupies in `matches.free`.
+pub trait Positionals {
+    /// Register a positional with the given `name`, returning the index it
+    /// was assigned. `variadic` marks a `VariadicPositional`, which consumes
+    /// the remainder of `matches.free` rather than a single slot.
+    fn add(&mut self, name: String, variadic: bool) -> usize;
+}
+
+/// Counts registered positionals and remembers whether a variadic was seen,
+/// so a real parse can tell whether leftover `free` tokens are unhandled.
+#[derive(Default)]
+pub struct PositionalsRegistrar {
+    count: usize,
+    has_variadic: bool,
+}
+
+impl PositionalsRegistrar {
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn has_variadic(&self) -> bool {
+        self.has_variadic
+    }
+}
+
+impl Positionals for PositionalsRegistrar {
+    fn add(&mut self, _name: String, variadic: bool) -> usize {
+        let index = self.count;
+        self.count += 1;
+        if variadic {
+            self.has_variadic = true;
+        }
+        index
+    }
+}
+
+/// Reports a positional-argument spec that can never be satisfied, mirroring
+/// `validation::Invalid` for named switches.
+#[derive(Debug)]
+pub enum PositionalsInvalid {
+    MultipleVariadicPositionalArgs { first: String, second: String },
+    VariadicPositionalArgNotLast { variadic: String, after: String },
+}
+
+impl fmt::Display for PositionalsInvalid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::MultipleVariadicPositionalArgs { first, second } => write!(
+                f,
+                "more than one variadic positional argument ({} and {})",
+                first, second
+            ),
+            Self::VariadicPositionalArgNotLast { variadic, after } => write!(
+                f,
+                "variadic positional argument ({}) must be registered last (found {} after it)",
+                variadic, after
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PositionalsChecker {
+    variadic: Option<String>,
+    invalid: Option<PositionalsInvalid>,
+}
+
+impl PositionalsChecker {
+    pub fn invalid(self) -> Option<PositionalsInvalid> {
+        self.invalid
+    }
+}
+
+impl Positionals for PositionalsChecker {
+    fn add(&mut self, name: String, variadic: bool) -> usize {
+        if let Some(earlier) = self.variadic.clone() {
+            if variadic {
+                self.invalid.get_or_insert(PositionalsInvalid::MultipleVariadicPositionalArgs {
+                    first: earlier,
+                    second: name.clone(),
+                });
+            } else {
+                self.invalid.get_or_insert(PositionalsInvalid::VariadicPositionalArgNotLast {
+                    variadic: earlier,
+                    after: name.clone(),
+                });
+            }
+        }
+        if variadic {
+            self.variadic = Some(name);
+        }
+        0
+    }
+}
+
+pub struct Positional {
+    name: String,
+    hint: String,
+    index: Cell<Option<usize>>,
+}
+
+impl Positional {
+    pub fn new(name: &str, hint: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            hint: hint.to_string(),
+            index: Cell::new(None),
+        }
+    }
+}
+
+impl Arg for Positional {
+    type Item = Option<String>;
+    type Error = Never;
+    fn update_switches<S: Switches>(&self, _switches: &mut S) {}
+    fn update_positionals<P: Positionals>(&self, positionals: &mut P) {
+        self.index.set(Some(positionals.add(self.name.clone(), false)));
+    }
+    fn name(&self) -> String {
+        format!("{} ({})", self.name, self.hint)
+    }
+    fn get(self, matches: &Matches) -> Result<Self::Item, Self::Error> {
+        let index = self
+            .index
+            .get()
+            .expect("update_positionals must be called before get");
+        Ok(matches.free.get(index).cloned())
+    }
+}
+
+pub struct VariadicPositional {
+    name: String,
+    hint: String,
+    index: Cell<Option<usize>>,
+}
+
+impl VariadicPositional {
+    pub fn new(name: &str, hint: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            hint: hint.to_string(),
+            index: Cell::new(None),
+        }
+    }
+}
+
+impl Arg for VariadicPositional {
+    type Item = Vec<String>;
+    type Error = Never;
+    fn update_switches<S: Switches>(&self, _switches: &mut S) {}
+    fn update_positionals<P: Positionals>(&self, positionals: &mut P) {
+        self.index.set(Some(positionals.add(self.name.clone(), true)));
+    }
+    fn name(&self) -> String {
+        format!("{} ({})", self.name, self.hint)
+    }
+    fn get(self, matches: &Matches) -> Result<Self::Item, Self::Error> {
+        let index = self
+            .index
+            .get()
+            .expect("update_positionals must be called before get");
+        Ok(matches.free.get(index..).unwrap_or(&[]).to_vec())
+    }
+}
+
+/// A single required-or-optional positional argument, analogous to `opt`.
+pub fn pos<T>(name: &str, hint: &str) -> impl Arg<Item = Option<T>>
+where
+    T: FromStr,
+    <T as FromStr>::Err: fmt::Debug + fmt::Display,
+{
+    Positional::new(name, hint).option_convert_string(|s| s.parse())
+}
+
+/// A positional argument that consumes every remaining `free` token.
+pub fn variadic<T>(name: &str, hint: &str) -> impl Arg<Item = Vec<T>>
+where
+    T: FromStr,
+    <T as FromStr>::Err: fmt::Debug + fmt::Display,
+{
+    VariadicPositional::new(name, hint).convert_strings(|s| s.parse())
+}
@@ -0,0 +1,331 @@
+//! Git-style subcommand dispatch.
+//!
+//! `parse_specified_ignoring_validation` runs a single low-level parse over
+//! all of `argv`, which can't express "the meaning of everything after this
+//! token depends on what this token is". `Subcommand` instead looks for the
+//! first non-switch token itself, matches it against a list of registered
+//! `Command`s, and hands the *remaining* tokens to only that command's
+//! `Arg`, each with its own `Usage`.
+//!
+//! Locating that first token (`split_argv`) is done without consulting a
+//! switch table, so any global switch allowed before the subcommand name
+//! (such as `SubcommandWithHelp`'s `--help`) must be a value-less flag; see
+//! `split_argv` for why a value-taking global option would misparse.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fmt;
+use std::process;
+
+use crate::{Arg, Flag, ParseResult, SwitchCommon, TopLevelError};
+
+/// One named subcommand and the `Arg` that parses its tail of `argv`.
+///
+/// Every `Command` in a `subcommands!` list must share an `Item`/`Error`
+/// pair (stricter than `Choice`, whose two branches need only share an
+/// `Item`): build that shared `Item` by `.map`-ing each subcommand's own
+/// result into a common enum before listing it here.
+pub struct Command<A> {
+    name: String,
+    arg: A,
+}
+
+pub fn command<A: Arg>(name: &str, arg: A) -> Command<A> {
+    Command {
+        name: name.to_string(),
+        arg,
+    }
+}
+
+impl<A: Arg> Command<A> {
+    pub fn then<B>(self, tail: B) -> Subcommands<A, B>
+    where
+        B: SubcommandList<Item = A::Item, Error = A::Error>,
+    {
+        Subcommands { head: self, tail }
+    }
+}
+
+/// A (possibly nested) list of `Command`s, built by `subcommands!`.
+pub trait SubcommandList: Sized {
+    type Item;
+    type Error: fmt::Debug + fmt::Display;
+    fn names(&self) -> Vec<String>;
+    fn dispatch(self, name: &str, rest: &[String]) -> Option<ParseResult<Self::Item, Self::Error>>;
+}
+
+impl<A: Arg> SubcommandList for Command<A> {
+    type Item = A::Item;
+    type Error = A::Error;
+    fn names(&self) -> Vec<String> {
+        vec![self.name.clone()]
+    }
+    fn dispatch(self, name: &str, rest: &[String]) -> Option<ParseResult<Self::Item, Self::Error>> {
+        if name == self.name {
+            Some(self.arg.parse_specified(self.name.clone(), rest))
+        } else {
+            None
+        }
+    }
+}
+
+pub struct Subcommands<A, B> {
+    head: Command<A>,
+    tail: B,
+}
+
+impl<A, B> SubcommandList for Subcommands<A, B>
+where
+    A: Arg,
+    B: SubcommandList<Item = A::Item, Error = A::Error>,
+{
+    type Item = A::Item;
+    type Error = A::Error;
+    fn names(&self) -> Vec<String> {
+        let mut names = self.head.names();
+        names.extend(self.tail.names());
+        names
+    }
+    fn dispatch(self, name: &str, rest: &[String]) -> Option<ParseResult<Self::Item, Self::Error>> {
+        let Self { head, tail } = self;
+        match head.dispatch(name, rest) {
+            Some(result) => Some(result),
+            None => tail.dispatch(name, rest),
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! subcommands {
+    ( $only:expr ) => {
+        $only
+    };
+    ( $head:expr, $($tail:expr),* $(,)* ) => {
+        $head.then($crate::subcommands!($($tail),*))
+    };
+}
+
+#[derive(Debug)]
+pub enum SubcommandError<E> {
+    NoSubcommandGiven,
+    UnknownSubcommand { name: String },
+    Arg(TopLevelError<E>),
+}
+
+impl<E: fmt::Display> fmt::Display for SubcommandError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::NoSubcommandGiven => write!(f, "no subcommand given"),
+            Self::UnknownSubcommand { name } => write!(f, "unknown subcommand ({})", name),
+            Self::Arg(error) => fmt::Display::fmt(error, f),
+        }
+    }
+}
+
+/// Top-level usage for a `Subcommand`/`SubcommandWithHelp`: just the list of
+/// names, since rendering any one subcommand's own switches means recursing
+/// into its own `Usage` (produced once that subcommand has actually been
+/// dispatched to).
+pub struct SubcommandsUsage {
+    program_name: String,
+    names: Vec<String>,
+}
+
+impl SubcommandsUsage {
+    pub fn render(&self) -> String {
+        let mut usage = format!(
+            "Usage: {} <subcommand> [args]\n\nSubcommands:\n",
+            self.program_name
+        );
+        for name in &self.names {
+            usage.push_str(&format!("    {}\n", name));
+        }
+        usage
+    }
+}
+
+pub struct SubcommandParseResult<I, E> {
+    pub usage: SubcommandsUsage,
+    pub result: Result<I, SubcommandError<E>>,
+}
+
+/// Splits `argv` into switches preceding the subcommand name, the name
+/// itself (the first non-switch token, or the token after a bare `--`),
+/// and the remaining tokens to hand to that subcommand's `Arg`.
+///
+/// There is no registered-switch table here (unlike `lowlevel::parse`), so
+/// this has no way to know whether a leading `-x`/`--xyz` token expects a
+/// parameter: every leading token starting with `-` is assumed to be a
+/// value-less flag (e.g. the top-level `--help`), and the first token that
+/// doesn't start with `-` is taken as the subcommand name. A global option
+/// that takes a value (e.g. `--config foo`) placed before the subcommand
+/// will misparse its value as the subcommand name; global args shared
+/// across subcommands must be flags.
+fn split_argv(args: Vec<String>) -> (Vec<String>, Option<String>, Vec<String>) {
+    let mut iter = args.into_iter();
+    let mut leading = Vec::new();
+    loop {
+        match iter.next() {
+            None => return (leading, None, Vec::new()),
+            Some(token) if token == "--" => {
+                return (leading, iter.next(), iter.collect());
+            }
+            Some(token) if token.starts_with('-') && token != "-" => {
+                leading.push(token);
+            }
+            Some(token) => return (leading, Some(token), iter.collect()),
+        }
+    }
+}
+
+fn leading_matches(leading: &[String], common: &SwitchCommon) -> bool {
+    leading.iter().any(|token| {
+        if let Some(long) = token.strip_prefix("--") {
+            common.long.as_deref() == Some(long)
+        } else if let Some(short) = token.strip_prefix('-') {
+            common.short.map(|c| c.to_string()).as_deref() == Some(short)
+        } else {
+            false
+        }
+    })
+}
+
+pub struct Subcommand<L> {
+    list: L,
+}
+
+pub fn subcommand<L: SubcommandList>(list: L) -> Subcommand<L> {
+    Subcommand { list }
+}
+
+impl<L: SubcommandList> Subcommand<L> {
+    pub fn parse_specified<I>(
+        self,
+        program_name: String,
+        args: I,
+    ) -> SubcommandParseResult<L::Item, L::Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<OsStr>,
+    {
+        let usage = SubcommandsUsage {
+            program_name,
+            names: self.list.names(),
+        };
+        let args = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        let (_leading, name, rest) = split_argv(args);
+        let result = match name {
+            None => Err(SubcommandError::NoSubcommandGiven),
+            Some(name) => match self.list.dispatch(&name, &rest) {
+                Some(parse_result) => parse_result.result.map_err(SubcommandError::Arg),
+                None => Err(SubcommandError::UnknownSubcommand { name }),
+            },
+        };
+        SubcommandParseResult { usage, result }
+    }
+
+    pub fn parse_env(self) -> SubcommandParseResult<L::Item, L::Error> {
+        let args = env::args().collect::<Vec<_>>();
+        let program_name = args[0].clone();
+        self.parse_specified(program_name, &args[1..])
+    }
+
+    pub fn parse_env_or_exit(self) -> L::Item {
+        let result = self.parse_env();
+        match result.result {
+            Ok(value) => value,
+            Err(error) => {
+                eprint!("{}\n\n", error);
+                eprint!("{}", result.usage.render());
+                process::exit(1);
+            }
+        }
+    }
+
+    pub fn with_help(self, help_flag: Flag) -> SubcommandWithHelp<L> {
+        SubcommandWithHelp {
+            subcommand: self,
+            help_flag,
+        }
+    }
+
+    pub fn with_help_default(self) -> SubcommandWithHelp<L> {
+        self.with_help(Flag::new(crate::names!['h', "help"], "print this help menu"))
+    }
+}
+
+pub enum SubcommandOrHelp<T> {
+    Value(T),
+    Help,
+}
+
+pub struct SubcommandWithHelp<L> {
+    subcommand: Subcommand<L>,
+    help_flag: Flag,
+}
+
+impl<L: SubcommandList> SubcommandWithHelp<L> {
+    pub fn parse_specified<I>(
+        self,
+        program_name: String,
+        args: I,
+    ) -> SubcommandParseResult<SubcommandOrHelp<L::Item>, L::Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<OsStr>,
+    {
+        let Self {
+            subcommand,
+            help_flag,
+        } = self;
+        let usage = SubcommandsUsage {
+            program_name,
+            names: subcommand.list.names(),
+        };
+        let args = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        let (leading, name, rest) = split_argv(args);
+        let result = if leading_matches(&leading, &help_flag.common) {
+            Ok(SubcommandOrHelp::Help)
+        } else {
+            match name {
+                None => Err(SubcommandError::NoSubcommandGiven),
+                Some(name) => match subcommand.list.dispatch(&name, &rest) {
+                    Some(parse_result) => parse_result
+                        .result
+                        .map(SubcommandOrHelp::Value)
+                        .map_err(SubcommandError::Arg),
+                    None => Err(SubcommandError::UnknownSubcommand { name }),
+                },
+            }
+        };
+        SubcommandParseResult { usage, result }
+    }
+
+    pub fn parse_env(self) -> SubcommandParseResult<SubcommandOrHelp<L::Item>, L::Error> {
+        let args = env::args().collect::<Vec<_>>();
+        let program_name = args[0].clone();
+        self.parse_specified(program_name, &args[1..])
+    }
+
+    pub fn parse_env_or_exit(self) -> L::Item {
+        let result = self.parse_env();
+        match result.result {
+            Ok(SubcommandOrHelp::Value(value)) => value,
+            Ok(SubcommandOrHelp::Help) => {
+                print!("{}", result.usage.render());
+                process::exit(0);
+            }
+            Err(error) => {
+                eprint!("{}\n\n", error);
+                eprint!("{}", result.usage.render());
+                process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use std::fmt;
+
+/// A type with no values, used as the `Error` of `Arg` impls that can never
+/// actually fail (e.g. reading a plain flag or string option).
+#[derive(Debug)]
+pub enum Never {}
+
+impl Never {
+    /// Unwraps a `Result` that's statically known to never be an `Err`.
+    pub fn result_ok<T>(result: Result<T, Never>) -> T {
+        match result {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+}
+
+impl fmt::Display for Never {
+    fn fmt(&self, _f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {}
+    }
+}
@@ -0,0 +1,65 @@
+//! Validates the switches registered by an `Arg` tree before parsing.
+//!
+//! `update_switches` just accumulates whatever it's given; nothing about the
+//! combinator machinery stops two leaves from registering the same name, or
+//! a leaf from registering neither a short nor a long one. `Checker` walks
+//! the tree the same way `Spec` does and reports the first such problem it
+//! finds, so `parse_specified` can panic on an un-satisfiable spec instead of
+//! silently misbehaving at parse time.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::{SwitchCommon, SwitchShape, Switches};
+
+#[derive(Debug)]
+pub enum Invalid {
+    DuplicateShortName(char),
+    DuplicateLongName(String),
+    NoName,
+}
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::DuplicateShortName(c) => write!(f, "duplicate short name (-{})", c),
+            Self::DuplicateLongName(name) => write!(f, "duplicate long name (--{})", name),
+            Self::NoName => write!(
+                f,
+                "a switch was declared with neither a short nor a long name"
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Checker {
+    short_names: HashSet<char>,
+    long_names: HashSet<String>,
+    invalid: Option<Invalid>,
+}
+
+impl Checker {
+    pub fn invalid(self) -> Option<Invalid> {
+        self.invalid
+    }
+}
+
+impl Switches for Checker {
+    fn add(&mut self, common: SwitchCommon, _shape: SwitchShape) {
+        if common.short.is_none() && common.long.is_none() {
+            self.invalid.get_or_insert(Invalid::NoName);
+            return;
+        }
+        if let Some(c) = common.short {
+            if !self.short_names.insert(c) {
+                self.invalid.get_or_insert(Invalid::DuplicateShortName(c));
+            }
+        }
+        if let Some(long) = common.long {
+            if !self.long_names.insert(long.clone()) {
+                self.invalid.get_or_insert(Invalid::DuplicateLongName(long));
+            }
+        }
+    }
+}